@@ -0,0 +1,329 @@
+//! An async counterpart to the blocking [`Transport`][super::Transport], built on `futures-io`
+//! and `async-trait`, for driving the smart HTTP protocol from within an async runtime without
+//! blocking a worker thread.
+use crate::{client, client::git, Protocol, Service};
+use async_trait::async_trait;
+use futures_io::{AsyncBufRead, AsyncRead};
+use std::{
+    borrow::Cow,
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use super::{
+    credentials,
+    options::Options,
+    traits::{Error, GetResponse, PostResponse},
+};
+use crate::client::SetProgressHandlerBufRead;
+
+/// The async counterpart to [`Http`][super::Http]: an abstraction over an async HTTP client
+/// capable of issuing the `GET`/`POST` requests the smart HTTP protocol needs.
+#[async_trait(?Send)]
+pub trait Http {
+    /// The type to read the response headers line by line.
+    type Headers: AsyncBufRead + Unpin + 'static;
+    /// The type to read the response body, also capable of deciphering sidebands for progress reporting.
+    type ResponseBody: AsyncBufRead + SetProgressHandlerBufRead + Unpin;
+    /// The type to stream the request body to the server.
+    type PostBody: futures_io::AsyncWrite + Unpin;
+
+    /// Issue an async `GET` request to `url` with extra `headers`, honoring `options`.
+    async fn get(
+        &mut self,
+        url: &str,
+        headers: impl IntoIterator<Item = impl AsRef<str>> + Send,
+        options: &Options,
+    ) -> Result<GetResponse<Self::Headers, Self::ResponseBody>, Error>;
+
+    /// Issue an async `POST` request to `url` with extra `headers`, honoring `options`.
+    async fn post(
+        &mut self,
+        url: &str,
+        headers: impl IntoIterator<Item = impl AsRef<str>> + Send,
+        options: &Options,
+    ) -> Result<PostResponse<Self::Headers, Self::ResponseBody, Self::PostBody>, Error>;
+}
+
+/// The async counterpart to [`Transport`][super::Transport].
+pub struct Transport<H: Http> {
+    url: String,
+    user_agent_header: &'static str,
+    version: crate::Protocol,
+    http: H,
+    service: Option<Service>,
+    line_provider: Option<git_packetline::AsyncProvider<H::ResponseBody>>,
+    options: Options,
+    identity: Option<credentials::Identity>,
+}
+
+impl<H: Http + Default> Transport<H> {
+    pub fn new(url: &str, version: crate::Protocol) -> Self {
+        Transport {
+            url: url.to_owned(),
+            user_agent_header: concat!("User-Agent: git/oxide-", env!("CARGO_PKG_VERSION")),
+            version,
+            service: None,
+            http: H::default(),
+            line_provider: None,
+            options: Options::default(),
+            identity: None,
+        }
+    }
+}
+
+impl<H: Http> Transport<H> {
+    /// Provide mutable access to the options used for authentication and other aspects of this transport.
+    pub fn options_mut(&mut self) -> &mut Options {
+        &mut self.options
+    }
+
+    async fn check_content_type(service: Service, kind: &str, headers: H::Headers) -> Result<(), client::Error> {
+        use futures_lite::io::AsyncBufReadExt;
+        let wanted_content_type = format!("Content-Type: application/x-{}-{}", service.as_str(), kind);
+        let mut headers = headers;
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = headers.read_line(&mut line).await?;
+            if read == 0 {
+                break;
+            }
+            lines.push(line.trim_end().to_owned());
+        }
+        if !lines.iter().any(|l| l == &wanted_content_type) {
+            return Err(client::Error::Http(Error::Detail(format!(
+                "Didn't find '{}' header to indicate 'smart' protocol, and 'dumb' protocol is not supported.",
+                wanted_content_type
+            ))));
+        }
+        Ok(())
+    }
+
+    fn call_authenticate(
+        &mut self,
+        action: credentials::Action,
+    ) -> Result<Option<credentials::Identity>, client::Error> {
+        match self.options.authenticate.as_mut() {
+            Some(authenticate) => {
+                authenticate(action).map_err(|err| client::Error::Http(Error::Authentication(err.to_string())))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Perform a `GET`, retrying once with credentials obtained from the configured
+    /// [`AuthenticateFn`][super::AuthenticateFn] if the server responds with `401 Unauthorized`.
+    async fn authenticated_get(
+        &mut self,
+        url: &str,
+        extra_headers: &[Cow<'_, str>],
+    ) -> Result<GetResponse<H::Headers, H::ResponseBody>, client::Error> {
+        loop {
+            let mut headers: Vec<Cow<'_, str>> = extra_headers.to_vec();
+            if let Some(identity) = &self.identity {
+                headers.push(Cow::Owned(super::basic_authorization(identity)));
+            }
+            let response = self.http.get(url, headers.iter(), &self.options).await?;
+            if response.status == 401 {
+                if let Some(identity) = self.identity.take() {
+                    self.call_authenticate(credentials::Action::Reject {
+                        url: url.to_owned(),
+                        identity,
+                    })?;
+                    return Err(client::Error::Http(Error::Authentication(
+                        "The previously provided credentials were rejected".into(),
+                    )));
+                }
+                self.identity = self.call_authenticate(credentials::Action::Fill { url: url.to_owned() })?;
+                if self.identity.is_none() {
+                    return Err(client::Error::Http(Error::Authentication(
+                        "The server requires authentication, but no credentials were provided".into(),
+                    )));
+                }
+                continue;
+            }
+            if let Some(identity) = self.identity.clone() {
+                self.call_authenticate(credentials::Action::Approve {
+                    url: url.to_owned(),
+                    identity,
+                })?;
+            }
+            return Ok(response);
+        }
+    }
+}
+
+fn append_url(base: &str, suffix: &str) -> String {
+    if base.ends_with('/') {
+        format!("{}{}", base, suffix)
+    } else {
+        format!("{}/{}", base, suffix)
+    }
+}
+
+#[async_trait(?Send)]
+impl<H: Http> client::AsyncTransport for Transport<H> {
+    async fn handshake(&mut self, service: Service) -> Result<client::SetServiceResponse, client::Error> {
+        let url = append_url(&self.url, &format!("info/refs?service={}", service.as_str()));
+        let mut headers = vec![Cow::Borrowed(self.user_agent_header)];
+        if self.version != Protocol::V1 {
+            headers.push(Cow::Owned(format!("Git-Protocol: version={}", self.version as usize)));
+        }
+        let GetResponse { headers, body, .. } = self.authenticated_get(&url, &headers).await?;
+        <Transport<H>>::check_content_type(service, "advertisement", headers).await?;
+
+        use futures_lite::io::AsyncReadExt;
+        let line_reader = self
+            .line_provider
+            .get_or_insert_with(|| git_packetline::AsyncProvider::new(body, git_packetline::PacketLine::Flush));
+
+        let mut announced_service = String::new();
+        line_reader.as_read().read_to_string(&mut announced_service).await?;
+        let expected_service_announcement = format!("# service={}", service.as_str());
+        if announced_service.trim() != expected_service_announcement {
+            return Err(client::Error::Http(Error::Detail(format!(
+                "Expected to see {:?}, but got {:?}",
+                expected_service_announcement,
+                announced_service.trim()
+            ))));
+        }
+
+        let (capabilities, refs) = git::recv::capabilties_and_possibly_refs_async(line_reader, self.version).await?;
+        self.service = Some(service);
+        Ok(client::SetServiceResponse {
+            actual_protocol: self.version,
+            capabilities,
+            refs,
+        })
+    }
+
+    async fn request(
+        &mut self,
+        write_mode: client::WriteMode,
+        on_drop: Vec<client::MessageKind>,
+    ) -> Result<client::AsyncRequestWriter, client::Error> {
+        let service = self.service.expect("handshake() must have been called first");
+        let url = append_url(&self.url, service.as_str());
+        let mut headers = vec![
+            format!("Content-Type: application/x-git-{}-request", service.as_str()),
+            format!("Accept: application/x-git-{}-result", service.as_str()),
+            "Expect:".into(),
+        ];
+        if let Some(identity) = &self.identity {
+            headers.push(super::basic_authorization(identity));
+        }
+        // Unlike `invoke()`, this `POST`'s body is written by the caller through the returned
+        // `AsyncRequestWriter`, after this call has already returned - there's no point here at
+        // which the transfer could be forced to complete to inspect its real status before handing
+        // `body` off, the way the blocking `Transport`'s `authenticated_post_with_body()` does for
+        // `invoke()`. By the time this runs, `handshake()`'s `GET` has already driven the full
+        // credentials-helper retry loop, so `self.identity` - sent above - is normally already the
+        // one the server accepts. A `401` specific to this `POST` is not retried, and as the response
+        // headers are discarded below, its body is handed to the packet-line reader like that of
+        // any other response.
+        let PostResponse {
+            headers: _,
+            body,
+            post_body,
+            ..
+        } = self.http.post(&url, &headers, &self.options).await?;
+        // TODO: combine header handling with body reader, see the blocking `Transport` for details.
+        let line_provider = self
+            .line_provider
+            .as_mut()
+            .expect("handshake to have been called first");
+        line_provider.replace(body);
+        Ok(client::AsyncRequestWriter::new_from_bufread(
+            post_body,
+            Box::new(line_provider.as_read_without_sidebands()),
+            write_mode,
+            on_drop,
+        ))
+    }
+}
+
+enum HeaderCheck<H: Http> {
+    Pending(Option<H::Headers>),
+    Checking(Pin<Box<dyn Future<Output = Result<(), client::Error>>>>),
+    Done,
+}
+
+/// The async counterpart to [`HeadersThenBody`][super::HeadersThenBody]: defers the content-type
+/// check to the first poll of the body, rather than performing it eagerly.
+struct HeadersThenBody<H: Http, B> {
+    service: Service,
+    headers: HeaderCheck<H>,
+    body: B,
+}
+
+impl<H: Http, B> HeadersThenBody<H, B> {
+    fn poll_handle_headers(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        loop {
+            match &mut self.headers {
+                HeaderCheck::Done => return Poll::Ready(Ok(())),
+                HeaderCheck::Pending(headers) => {
+                    let headers = headers.take().expect("HeaderCheck::Pending always carries headers");
+                    let service = self.service;
+                    self.headers =
+                        HeaderCheck::Checking(Box::pin(
+                            async move { <Transport<H>>::check_content_type(service, "result", headers).await },
+                        ));
+                }
+                HeaderCheck::Checking(fut) => {
+                    return match fut.as_mut().poll(cx) {
+                        Poll::Ready(Ok(())) => {
+                            self.headers = HeaderCheck::Done;
+                            Poll::Ready(Ok(()))
+                        }
+                        Poll::Ready(Err(err)) => Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, err))),
+                        Poll::Pending => Poll::Pending,
+                    };
+                }
+            }
+        }
+    }
+}
+
+impl<H: Http, B: AsyncRead + Unpin> AsyncRead for HeadersThenBody<H, B> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        match this.poll_handle_headers(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut this.body).poll_read(cx, buf),
+            Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<H: Http, B: AsyncBufRead + Unpin> AsyncBufRead for HeadersThenBody<H, B> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        match this.poll_handle_headers(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut this.body).poll_fill_buf(cx),
+            Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+        Pin::new(&mut this.body).consume(amt)
+    }
+}
+
+impl<H: Http, B: SetProgressHandlerBufRead> SetProgressHandlerBufRead for HeadersThenBody<H, B> {
+    // As with the blocking `Transport`, the handler itself is only forwarded here - see
+    // `crate::client::HandleProgress` for the contract it's meant to fulfil, and the caveat that
+    // `git_packetline` doesn't honor it yet.
+    fn set_progress_handler(&mut self, handle_progress: Option<crate::client::HandleProgress>) {
+        self.body.set_progress_handler(handle_progress)
+    }
+}
+
+pub fn connect<H: Http + Default>(url: &str, version: crate::Protocol) -> Result<Transport<H>, std::convert::Infallible> {
+    Ok(Transport::new(url, version))
+}
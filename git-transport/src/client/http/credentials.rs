@@ -0,0 +1,34 @@
+/// A username and password pair obtained from a credentials helper, used to authenticate
+/// with the remote via HTTP Basic authentication.
+#[derive(Clone, Eq, PartialEq)]
+pub struct Identity {
+    pub username: String,
+    pub password: String,
+}
+
+impl std::fmt::Debug for Identity {
+    /// Redact `password` so it can't leak into logs or panic messages via a stray `{:?}`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Identity")
+            .field("username", &self.username)
+            .field("password", &"***")
+            .finish()
+    }
+}
+
+/// The action to ask the [`AuthenticateFn`][super::options::AuthenticateFn] to perform.
+pub enum Action {
+    /// Obtain an identity to use for authenticating against `url`.
+    Fill { url: String },
+    /// The `identity` previously obtained for `url` worked and should be remembered.
+    Approve { url: String, identity: Identity },
+    /// The `identity` previously obtained for `url` was rejected by the server and should be forgotten.
+    Reject { url: String, identity: Identity },
+}
+
+/// The error returned by an [`AuthenticateFn`][super::options::AuthenticateFn].
+pub type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// The result of calling an [`AuthenticateFn`][super::options::AuthenticateFn]: `Some(identity)` if
+/// one was provided (only ever expected for [`Action::Fill`]), or `None` otherwise.
+pub type Result = std::result::Result<Option<Identity>, Error>;
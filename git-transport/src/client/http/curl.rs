@@ -0,0 +1,278 @@
+use super::{
+    options::{FollowRedirects, HttpVersion, Options, SslVersion},
+    traits::{Error, GetResponse, Http, PostResponse, ResponseStatus},
+};
+use crate::client::SetProgressHandlerBufRead;
+use curl::easy::{Easy, List};
+use std::{
+    cell::{Cell, RefCell},
+    io,
+    rc::Rc,
+};
+
+/// A libcurl-based implementation of the [`Http`] trait, using the synchronous `curl` crate.
+#[derive(Default)]
+pub struct Curl;
+
+/// The headers of a response, readable line by line.
+pub struct Headers(io::Cursor<Vec<u8>>);
+
+impl io::Read for Headers {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        io::Read::read(&mut self.0, buf)
+    }
+}
+
+impl io::BufRead for Headers {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        io::BufRead::fill_buf(&mut self.0)
+    }
+    fn consume(&mut self, amt: usize) {
+        io::BufRead::consume(&mut self.0, amt)
+    }
+}
+
+/// The not-yet-written body of a `POST` request, filled in by the caller before the response is read.
+#[derive(Clone, Default)]
+pub struct PostBody(Rc<RefCell<Vec<u8>>>);
+
+impl io::Write for PostBody {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+enum Delayed {
+    /// A `GET`'s response is available right away.
+    Sent(io::Cursor<Vec<u8>>),
+    /// A `POST`'s request body is written by the caller only after this call returns, so the
+    /// transfer is actually performed lazily, once the caller starts reading the response and
+    /// `post_body` is therefore complete.
+    Pending {
+        handle: Easy,
+        post_body: PostBody,
+    },
+}
+
+/// The body of a response, read lazily to give a `POST`'s request body a chance to be written first.
+pub struct ResponseBody {
+    state: Delayed,
+    /// The real HTTP status, known right away for a `GET` but only filled in by `ensure_sent()`
+    /// for a `POST`, once the transfer has actually been performed.
+    status: Cell<u16>,
+    /// Stored by `set_progress_handler()` and never called: this body does not decode sidebands, so
+    /// it reports no progress or error lines.
+    handle_progress: Option<crate::client::HandleProgress>,
+}
+
+impl ResponseBody {
+    fn ensure_sent(&mut self) -> io::Result<()> {
+        if let Delayed::Pending { handle, post_body } = &mut self.state {
+            let request_body = post_body.0.borrow().clone();
+            handle
+                .post_field_size(request_body.len() as u64)
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, to_error(err)))?;
+            let (_headers, body, status) =
+                perform(handle, &request_body).map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+            self.status.set(status);
+            self.state = Delayed::Sent(io::Cursor::new(body));
+        }
+        Ok(())
+    }
+
+    fn cursor(&mut self) -> &mut io::Cursor<Vec<u8>> {
+        match &mut self.state {
+            Delayed::Sent(cursor) => cursor,
+            Delayed::Pending { .. } => unreachable!("ensure_sent() must be called first"),
+        }
+    }
+}
+
+impl ResponseStatus for ResponseBody {
+    fn status(&self) -> u16 {
+        self.status.get()
+    }
+}
+
+impl io::Read for ResponseBody {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.ensure_sent()?;
+        self.cursor().read(buf)
+    }
+}
+
+impl io::BufRead for ResponseBody {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.ensure_sent()?;
+        self.cursor().fill_buf()
+    }
+    fn consume(&mut self, amt: usize) {
+        self.cursor().consume(amt)
+    }
+}
+
+impl SetProgressHandlerBufRead for ResponseBody {
+    fn set_progress_handler(&mut self, handle_progress: Option<crate::client::HandleProgress>) {
+        self.handle_progress = handle_progress;
+    }
+}
+
+fn ssl_version(version: SslVersion) -> curl::easy::SslVersion {
+    match version {
+        SslVersion::SslV3 => curl::easy::SslVersion::Sslv3,
+        SslVersion::TlsV1_0 => curl::easy::SslVersion::Tlsv10,
+        SslVersion::TlsV1_1 => curl::easy::SslVersion::Tlsv11,
+        SslVersion::TlsV1_2 => curl::easy::SslVersion::Tlsv12,
+        SslVersion::TlsV1_3 => curl::easy::SslVersion::Tlsv13,
+    }
+}
+
+/// `http.followRedirects=initial` means the *initial* `info/refs` GET may redirect freely - the URL
+/// it lands on is then used for the rest of the session - while every other request must not
+/// redirect at all, so whether to follow depends on which request `is_initial_request` is for.
+fn configure_redirects(handle: &mut Easy, options: &Options, is_initial_request: bool) -> Result<(), curl::Error> {
+    let follow = match options.follow_redirects {
+        FollowRedirects::All => true,
+        FollowRedirects::Initial => is_initial_request,
+        FollowRedirects::None => false,
+    };
+    handle.follow_location(follow)
+}
+
+fn new_handle(options: &Options, is_initial_request: bool) -> Result<Easy, curl::Error> {
+    let mut handle = Easy::new();
+    configure_redirects(&mut handle, options, is_initial_request)?;
+    if let Some(version) = options.http_version {
+        handle.http_version(match version {
+            HttpVersion::Http1_1 => curl::easy::HttpVersion::V11,
+            HttpVersion::Http2 => curl::easy::HttpVersion::V2,
+        })?;
+    }
+    // Leave whichever bound wasn't given open, matching the reqwest backend: a lone `ssl_version_min`
+    // doesn't collapse the range to exactly that version.
+    if options.ssl_version_min.is_some() || options.ssl_version_max.is_some() {
+        let min = options.ssl_version_min.map(ssl_version).unwrap_or(curl::easy::SslVersion::Default);
+        let max = options.ssl_version_max.map(ssl_version).unwrap_or(curl::easy::SslVersion::Default);
+        handle.ssl_min_max_version(min, max)?;
+    }
+    if let Some(proxy) = &options.proxy {
+        handle.proxy(proxy)?;
+        if let Some(auth) = &options.proxy_authenticate {
+            handle.proxy_username(&auth.username)?;
+            handle.proxy_password(&auth.password)?;
+        }
+    }
+    Ok(handle)
+}
+
+fn headers_to_list(
+    headers: impl IntoIterator<Item = impl AsRef<str>>,
+    extra_headers: &[String],
+) -> Result<List, curl::Error> {
+    let mut list = List::new();
+    for header in headers {
+        list.append(header.as_ref())?;
+    }
+    for header in extra_headers {
+        list.append(header)?;
+    }
+    Ok(list)
+}
+
+fn perform(handle: &mut Easy, mut request_body: &[u8]) -> Result<(Headers, Vec<u8>, u16), Error> {
+    let mut header_buf = Vec::new();
+    let mut body_buf = Vec::new();
+    {
+        let mut transfer = handle.transfer();
+        transfer
+            .header_function(|line| {
+                header_buf.extend_from_slice(line);
+                true
+            })
+            .map_err(to_error)?;
+        transfer
+            .write_function(|data| {
+                body_buf.extend_from_slice(data);
+                Ok(data.len())
+            })
+            .map_err(to_error)?;
+        transfer
+            .read_function(move |into| Ok(io::Read::read(&mut request_body, into).unwrap_or(0)))
+            .map_err(to_error)?;
+        transfer.perform().map_err(to_error)?;
+    }
+    let status = handle.response_code().map_err(to_error)? as u16;
+    Ok((Headers(io::Cursor::new(header_buf)), body_buf, status))
+}
+
+fn to_error(err: curl::Error) -> Error {
+    Error::Detail(err.to_string())
+}
+
+impl Http for Curl {
+    type Headers = Headers;
+    type ResponseBody = ResponseBody;
+    type PostBody = PostBody;
+
+    fn get(
+        &mut self,
+        url: &str,
+        headers: impl IntoIterator<Item = impl AsRef<str>>,
+        options: &Options,
+    ) -> Result<GetResponse<Self::Headers, Self::ResponseBody>, Error> {
+        // `get()` is only ever used for the initial `info/refs` advertisement.
+        let mut handle = new_handle(options, true).map_err(to_error)?;
+        handle.url(url).map_err(to_error)?;
+        handle.get(true).map_err(to_error)?;
+        let list = headers_to_list(headers, &options.extra_headers).map_err(to_error)?;
+        handle.http_headers(list).map_err(to_error)?;
+        let (headers, body, status) = perform(&mut handle, &[])?;
+        Ok(GetResponse {
+            headers,
+            body: ResponseBody {
+                state: Delayed::Sent(io::Cursor::new(body)),
+                status: Cell::new(status),
+                handle_progress: None,
+            },
+            status,
+        })
+    }
+
+    fn post(
+        &mut self,
+        url: &str,
+        headers: impl IntoIterator<Item = impl AsRef<str>>,
+        options: &Options,
+    ) -> Result<PostResponse<Self::Headers, Self::ResponseBody, Self::PostBody>, Error> {
+        // Unlike a `GET`, the request body isn't known yet: the caller writes to `post_body`
+        // after this call returns, so the transfer is only actually performed once the caller
+        // starts reading the response, in `ResponseBody::ensure_sent()`.
+        //
+        // `post()` is never the initial request, so `FollowRedirects::Initial` must not follow here.
+        let mut handle = new_handle(options, false).map_err(to_error)?;
+        handle.url(url).map_err(to_error)?;
+        let list = headers_to_list(headers, &options.extra_headers).map_err(to_error)?;
+        handle.http_headers(list).map_err(to_error)?;
+        handle.post(true).map_err(to_error)?;
+        let post_body = PostBody::default();
+        Ok(PostResponse {
+            headers: Headers(io::Cursor::new(Vec::new())),
+            body: ResponseBody {
+                state: Delayed::Pending {
+                    handle,
+                    post_body: post_body.clone(),
+                },
+                status: Cell::new(0),
+                handle_progress: None,
+            },
+            post_body,
+            // Not known until the request body has been fully written and the transfer performed;
+            // see `Delayed::Pending` above and `ResponseBody`'s `ResponseStatus` impl.
+            status: 0,
+        })
+    }
+}
@@ -3,19 +3,31 @@ use std::{
     borrow::Cow,
     convert::Infallible,
     io,
-    io::{BufRead, Read},
+    io::{BufRead, Read, Write},
 };
 
+#[cfg(all(feature = "http-client-curl", feature = "http-client-reqwest"))]
+compile_error!("Cannot set both 'http-client-curl' and 'http-client-reqwest' features as they are mutually exclusive");
+
 #[cfg(feature = "http-client-curl")]
 pub(crate) mod curl;
+#[cfg(feature = "http-client-reqwest")]
+pub(crate) mod reqwest;
 
+pub mod credentials;
+pub mod options;
 mod traits;
+#[cfg(feature = "futures-io")]
+pub mod async_io;
 use crate::client::{HandleProgress, RequestWriter, SetProgressHandlerBufRead};
 use git_packetline::PacketLine;
-pub use traits::{Error, GetResponse, Http, PostResponse};
+pub use options::{AuthenticateFn, Options};
+pub use traits::{Error, GetResponse, Http, PostResponse, ResponseStatus};
 
 #[cfg(feature = "http-client-curl")]
 pub type Impl = curl::Curl;
+#[cfg(feature = "http-client-reqwest")]
+pub type Impl = reqwest::Reqwest;
 
 pub struct Transport<H: Http> {
     url: String,
@@ -24,6 +36,8 @@ pub struct Transport<H: Http> {
     http: H,
     service: Option<Service>,
     line_provider: Option<git_packetline::Provider<H::ResponseBody>>,
+    options: Options,
+    identity: Option<credentials::Identity>,
 }
 
 impl Transport<Impl> {
@@ -35,11 +49,18 @@ impl Transport<Impl> {
             service: None,
             http: Impl::default(),
             line_provider: None,
+            options: Options::default(),
+            identity: None,
         }
     }
 }
 
 impl<H: Http> Transport<H> {
+    /// Provide mutable access to the options used for authentication and other aspects of this transport.
+    pub fn options_mut(&mut self) -> &mut Options {
+        &mut self.options
+    }
+
     fn check_content_type(service: Service, kind: &str, headers: <H as Http>::Headers) -> Result<(), client::Error> {
         let wanted_content_type = format!("Content-Type: application/x-{}-{}", service.as_str(), kind);
         if !headers
@@ -55,6 +76,182 @@ impl<H: Http> Transport<H> {
         }
         Ok(())
     }
+
+    fn call_authenticate(
+        &mut self,
+        action: credentials::Action,
+    ) -> Result<Option<credentials::Identity>, client::Error> {
+        match self.options.authenticate.as_mut() {
+            Some(authenticate) => {
+                authenticate(action).map_err(|err| client::Error::Http(Error::Authentication(err.to_string())))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Perform a `GET`, retrying once with credentials obtained from the configured
+    /// [`AuthenticateFn`] if the server responds with `401 Unauthorized`.
+    fn authenticated_get(
+        &mut self,
+        url: &str,
+        extra_headers: &[Cow<'_, str>],
+    ) -> Result<GetResponse<H::Headers, H::ResponseBody>, client::Error> {
+        loop {
+            let mut headers: Vec<Cow<'_, str>> = extra_headers.to_vec();
+            if let Some(identity) = &self.identity {
+                headers.push(Cow::Owned(basic_authorization(identity)));
+            }
+            let response = self.http.get(url, headers.iter(), &self.options)?;
+            if response.status == 401 {
+                if let Some(identity) = self.identity.take() {
+                    self.call_authenticate(credentials::Action::Reject {
+                        url: url.to_owned(),
+                        identity,
+                    })?;
+                    return Err(client::Error::Http(Error::Authentication(
+                        "The previously provided credentials were rejected".into(),
+                    )));
+                }
+                self.identity = self.call_authenticate(credentials::Action::Fill { url: url.to_owned() })?;
+                if self.identity.is_none() {
+                    return Err(client::Error::Http(Error::Authentication(
+                        "The server requires authentication, but no credentials were provided".into(),
+                    )));
+                }
+                continue;
+            }
+            if let Some(identity) = self.identity.clone() {
+                self.call_authenticate(credentials::Action::Approve {
+                    url: url.to_owned(),
+                    identity,
+                })?;
+            }
+            return Ok(response);
+        }
+    }
+
+    /// Perform a `POST` whose entire request `body` is already known, retrying once with
+    /// credentials obtained from the configured [`AuthenticateFn`] if the server responds with
+    /// `401 Unauthorized`.
+    ///
+    /// Both backends make `Http::post()` lazy: it returns before `body` has even been sent, with a
+    /// placeholder `status` of `0`, and only actually performs the transfer once its response body
+    /// is first read. Since `body` is fully known upfront here (unlike
+    /// [`request()`][client::Transport::request()], whose body is written by an external caller
+    /// after the call returns), we can write it, force the transfer to complete by reading the
+    /// response once, and check its *real* status via [`ResponseStatus::status()`] before deciding
+    /// whether to retry - checking the placeholder `status` field, as a naive retry loop would,
+    /// never observes a `401` at all.
+    fn authenticated_post_with_body(
+        &mut self,
+        url: &str,
+        extra_headers: &[String],
+        body: &[u8],
+    ) -> Result<PostResponse<H::Headers, H::ResponseBody, H::PostBody>, client::Error> {
+        loop {
+            let mut headers = extra_headers.to_vec();
+            if let Some(identity) = &self.identity {
+                headers.push(basic_authorization(identity));
+            }
+            let mut response = self.http.post(url, &headers, &self.options)?;
+            response.post_body.write_all(body)?;
+            response.post_body.flush()?;
+            response.body.fill_buf()?;
+            let status = response.body.status();
+            if status == 401 {
+                if let Some(identity) = self.identity.take() {
+                    self.call_authenticate(credentials::Action::Reject {
+                        url: url.to_owned(),
+                        identity,
+                    })?;
+                    return Err(client::Error::Http(Error::Authentication(
+                        "The previously provided credentials were rejected".into(),
+                    )));
+                }
+                self.identity = self.call_authenticate(credentials::Action::Fill { url: url.to_owned() })?;
+                if self.identity.is_none() {
+                    return Err(client::Error::Http(Error::Authentication(
+                        "The server requires authentication, but no credentials were provided".into(),
+                    )));
+                }
+                continue;
+            }
+            if let Some(identity) = self.identity.clone() {
+                self.call_authenticate(credentials::Action::Approve {
+                    url: url.to_owned(),
+                    identity,
+                })?;
+            }
+            return Ok(response);
+        }
+    }
+
+    /// Invoke a single protocol V2 `command`, such as `ls-refs` or `fetch`, with its `capabilities`
+    /// and `args`, returning a reader for its response body.
+    ///
+    /// Unlike [`request()`][client::Transport::request()], which drives the single stateful V1
+    /// negotiation, this packages the entire command as a self-contained packet-line request body
+    /// and POSTs it independently: V2 over HTTP is stateless, so every command is its own
+    /// round-trip rather than a continuation of `handshake()`'s advertisement response.
+    pub fn invoke(
+        &mut self,
+        command: &str,
+        capabilities: impl IntoIterator<Item = (impl Into<String>, Option<impl Into<String>>)>,
+        args: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Result<Box<dyn BufRead + '_>, client::Error> {
+        assert_eq!(
+            self.version,
+            crate::Protocol::V2,
+            "BUG: invoke() only makes sense when using protocol version 2"
+        );
+        let service = self.service.expect("handshake() must have been called first");
+        let url = append_url(&self.url, service.as_str());
+        let headers = [
+            format!("Content-Type: application/x-git-{}-request", service.as_str()),
+            format!("Accept: application/x-git-{}-result", service.as_str()),
+            "Expect:".into(),
+            // Every stateless-RPC request must carry this, not just the initial advertisement GET,
+            // or a real server has no signal that this POST body is V2 framing rather than legacy V1.
+            format!("Git-Protocol: version={}", self.version as usize),
+        ];
+        // Built upfront, rather than written straight to the POST body as before, so a `401` can be
+        // retried by resending the exact same bytes against a fresh `POST` - see
+        // `authenticated_post_with_body()`.
+        let mut body = Vec::new();
+        write_pkt_line(&mut body, format!("command={}", command).as_bytes())?;
+        for (key, value) in capabilities {
+            let key = key.into();
+            let line = match value {
+                Some(value) => format!("{}={}", key, value.into()),
+                None => key,
+            };
+            write_pkt_line(&mut body, line.as_bytes())?;
+        }
+        write_delim_pkt(&mut body)?;
+        for arg in args {
+            write_pkt_line(&mut body, arg.into().as_bytes())?;
+        }
+        write_flush_pkt(&mut body)?;
+
+        let PostResponse { body, .. } = self.authenticated_post_with_body(&url, &headers, &body)?;
+
+        // Every invocation is independent, so it gets its own line provider rather than reusing
+        // one left over from `handshake()`'s capability advertisement.
+        self.line_provider = Some(git_packetline::Provider::new(body, PacketLine::Flush));
+        Ok(Box::new(
+            self.line_provider
+                .as_mut()
+                .expect("line_provider was just set")
+                .as_read_without_sidebands(),
+        ))
+    }
+}
+
+fn basic_authorization(identity: &credentials::Identity) -> String {
+    format!(
+        "Authorization: Basic {}",
+        base64::encode(format!("{}:{}", identity.username, identity.password))
+    )
 }
 
 fn append_url(base: &str, suffix: &str) -> String {
@@ -65,15 +262,30 @@ fn append_url(base: &str, suffix: &str) -> String {
     }
 }
 
+/// Write `data` as a single protocol V2 packet-line, prefixed with its 4-hex-digit length.
+fn write_pkt_line(out: &mut impl io::Write, data: &[u8]) -> io::Result<()> {
+    write!(out, "{:04x}", data.len() + 4)?;
+    out.write_all(data)
+}
+
+/// Write the special `0000` flush-packet that ends a command's argument list.
+fn write_flush_pkt(out: &mut impl io::Write) -> io::Result<()> {
+    out.write_all(b"0000")
+}
+
+/// Write the special `0001` delimiter-packet that separates a command's capabilities from its arguments.
+fn write_delim_pkt(out: &mut impl io::Write) -> io::Result<()> {
+    out.write_all(b"0001")
+}
+
 impl<H: Http> client::Transport for Transport<H> {
     fn handshake(&mut self, service: Service) -> Result<client::SetServiceResponse, client::Error> {
         let url = append_url(&self.url, &format!("info/refs?service={}", service.as_str()));
-        let static_headers = [Cow::Borrowed(self.user_agent_header)];
-        let mut dynamic_headers = Vec::<Cow<str>>::new();
+        let mut headers = vec![Cow::Borrowed(self.user_agent_header)];
         if self.version != Protocol::V1 {
-            dynamic_headers.push(Cow::Owned(format!("Git-Protocol: version={}", self.version as usize)));
+            headers.push(Cow::Owned(format!("Git-Protocol: version={}", self.version as usize)));
         }
-        let GetResponse { headers, body } = self.http.get(&url, static_headers.iter().chain(&dynamic_headers))?;
+        let GetResponse { headers, body, .. } = self.authenticated_get(&url, &headers)?;
         <Transport<H>>::check_content_type(service, "advertisement", headers)?;
 
         let line_reader = self
@@ -91,6 +303,9 @@ impl<H: Http> client::Transport for Transport<H> {
             ))));
         }
 
+        // V2's advertisement carries capabilities only, never a ref list - `refs` is `None` in that
+        // case. Any following commands (`ls-refs`, `fetch`, ...) are driven through `invoke()`
+        // instead, each as its own independent POST, rather than through this advertisement response.
         let (capabilities, refs) = git::recv::capabilties_and_possibly_refs(line_reader, self.version)?;
         self.service = Some(service);
         Ok(client::SetServiceResponse {
@@ -107,16 +322,28 @@ impl<H: Http> client::Transport for Transport<H> {
     ) -> Result<client::RequestWriter, client::Error> {
         let service = self.service.expect("handshake() must have been called first");
         let url = append_url(&self.url, service.as_str());
-        let headers = &[
+        let mut headers = vec![
             format!("Content-Type: application/x-git-{}-request", service.as_str()),
             format!("Accept: application/x-git-{}-result", service.as_str()),
             "Expect:".into(),
         ];
+        if let Some(identity) = &self.identity {
+            headers.push(basic_authorization(identity));
+        }
+        // Unlike `invoke()`, this `POST`'s body is written by the caller through the returned
+        // `RequestWriter`, after this call has already returned - there's no point here at which
+        // the transfer could be forced to complete to inspect its real status before handing `body`
+        // off, the way `authenticated_post_with_body()` does. By the time this runs, `handshake()`'s
+        // `GET` has already driven the full credentials-helper retry loop, so `self.identity` - sent
+        // above - is normally already the one the server accepts. A `401` specific to this `POST`
+        // is not retried, and as the response headers are discarded below, its body is handed to
+        // the packet-line reader like that of any other response.
         let PostResponse {
             headers: _,
             body,
             post_body,
-        } = self.http.post(&url, headers)?;
+            ..
+        } = self.http.post(&url, &headers, &self.options)?;
         // TODO: combine header handling with body reader
         // <Transport<H>>::check_content_type(service, "result", headers)?;
         let line_provider = self
@@ -168,6 +395,9 @@ impl<H: Http, B: SetProgressHandlerBufRead> io::BufRead for HeadersThenBody<H, B
 }
 
 impl<H: Http, B: SetProgressHandlerBufRead> SetProgressHandlerBufRead for HeadersThenBody<H, B> {
+    // `handle_progress` is passed through as-is to `git_packetline`'s sideband-decoding reader -
+    // see `crate::client::HandleProgress` for the contract it's meant to fulfil, and the caveat
+    // that `git_packetline` doesn't honor it yet.
     fn set_progress_handler(&mut self, handle_progress: Option<HandleProgress>) {
         self.body.set_progress_handler(handle_progress)
     }
@@ -0,0 +1,79 @@
+use super::credentials;
+
+/// A function called to obtain, approve or reject credentials, as described by [`credentials::Action`].
+pub type AuthenticateFn = Box<dyn FnMut(credentials::Action) -> credentials::Result + Send>;
+
+/// Mirrors git's `http.followRedirects` configuration.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum FollowRedirects {
+    /// Follow all redirects encountered, which is curl's default.
+    All,
+    /// Only follow a redirect for the initial request, but not for subsequent ones, mirroring what
+    /// git does to avoid being sent somewhere unexpected when negotiating the actual transfer.
+    Initial,
+    /// Do not follow any redirects at all.
+    None,
+}
+
+impl Default for FollowRedirects {
+    fn default() -> Self {
+        FollowRedirects::Initial
+    }
+}
+
+/// The HTTP version to use or to restrict ourselves to.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum HttpVersion {
+    /// Use HTTP/1.1.
+    Http1_1,
+    /// Use HTTP/2.
+    Http2,
+}
+
+/// The SSL/TLS versions supported by curl, ordered from oldest to newest so a range of them can be formed.
+#[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Debug)]
+pub enum SslVersion {
+    /// SSLv3, highly discouraged and disabled by most servers.
+    SslV3,
+    /// TLSv1.0
+    TlsV1_0,
+    /// TLSv1.1
+    TlsV1_1,
+    /// TLSv1.2
+    TlsV1_2,
+    /// TLSv1.3
+    TlsV1_3,
+}
+
+/// Proxy authentication, set alongside [`Options::proxy`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ProxyAuthenticate {
+    /// The username to authenticate with the proxy.
+    pub username: String,
+    /// The password to authenticate with the proxy.
+    pub password: String,
+}
+
+/// Options to configure the behaviour of an HTTP [`Transport`][super::Transport].
+#[derive(Default)]
+pub struct Options {
+    /// A function to call when the server responds with `401 Unauthorized` to the `info/refs` `GET`
+    /// or to a protocol V2 `invoke()` `POST`, to obtain credentials to retry the request with, and to
+    /// inform about whether a previously provided identity worked. A `401` in response to the `POST`
+    /// of `request()` does not call it.
+    pub authenticate: Option<AuthenticateFn>,
+    /// How to deal with HTTP redirects, mirroring git's `http.followRedirects`.
+    pub follow_redirects: FollowRedirects,
+    /// The HTTP version to use, or `None` to leave the choice to the backend.
+    pub http_version: Option<HttpVersion>,
+    /// The lowest acceptable SSL/TLS version, or `None` for the backend's default.
+    pub ssl_version_min: Option<SslVersion>,
+    /// The highest acceptable SSL/TLS version, or `None` for the backend's default.
+    pub ssl_version_max: Option<SslVersion>,
+    /// The URL of a proxy to route all requests through, mirroring git's `http.proxy`.
+    pub proxy: Option<String>,
+    /// Credentials to authenticate with `proxy`, if it requires authentication of its own.
+    pub proxy_authenticate: Option<ProxyAuthenticate>,
+    /// Additional headers to send with every request, verbatim.
+    pub extra_headers: Vec<String>,
+}
@@ -0,0 +1,279 @@
+use super::{
+    options::{FollowRedirects, HttpVersion, Options, SslVersion},
+    traits::{Error, GetResponse, Http, PostResponse, ResponseStatus},
+};
+use crate::client::SetProgressHandlerBufRead;
+use std::{
+    cell::{Cell, RefCell},
+    io,
+    rc::Rc,
+};
+
+/// A reqwest-based implementation of the [`Http`] trait, using the `reqwest` crate's blocking client.
+///
+/// Unlike the curl-based backend, this one is pure Rust (modulo the TLS implementation chosen via
+/// `reqwest`'s own feature flags) and doesn't require linking against libcurl.
+#[derive(Default)]
+pub struct Reqwest;
+
+fn ssl_version(version: SslVersion) -> Option<reqwest::tls::Version> {
+    Some(match version {
+        // `reqwest` doesn't support SSLv3 at all, so this only narrows the range from above.
+        SslVersion::SslV3 => return None,
+        SslVersion::TlsV1_0 => reqwest::tls::Version::TLS_1_0,
+        SslVersion::TlsV1_1 => reqwest::tls::Version::TLS_1_1,
+        SslVersion::TlsV1_2 => reqwest::tls::Version::TLS_1_2,
+        SslVersion::TlsV1_3 => reqwest::tls::Version::TLS_1_3,
+    })
+}
+
+/// Like [`ssl_version`], but for `ssl_version_max`: leaving the upper bound unset means "no cap at
+/// all", so an unrepresentable `SslV3` has to clamp down to the lowest version `reqwest` can express
+/// instead of being dropped - the opposite of `ssl_version`'s "unbounded from below" treatment of it.
+fn ssl_version_max(version: SslVersion) -> reqwest::tls::Version {
+    ssl_version(version).unwrap_or(reqwest::tls::Version::TLS_1_0)
+}
+
+/// `http.followRedirects=initial` means the *initial* `info/refs` GET may redirect freely - the URL
+/// it lands on is then used for the rest of the session - while every other request must not
+/// redirect at all, so whether to follow depends on which request `is_initial_request` is for.
+fn client_for(options: &Options, is_initial_request: bool) -> reqwest::Result<reqwest::blocking::Client> {
+    let mut builder = reqwest::blocking::Client::builder();
+    let follow = match options.follow_redirects {
+        FollowRedirects::All => true,
+        FollowRedirects::Initial => is_initial_request,
+        FollowRedirects::None => false,
+    };
+    builder = builder.redirect(if follow {
+        reqwest::redirect::Policy::limited(10)
+    } else {
+        reqwest::redirect::Policy::none()
+    });
+    builder = match options.http_version {
+        Some(HttpVersion::Http1_1) => builder.http1_only(),
+        Some(HttpVersion::Http2) => builder.http2_prior_knowledge(),
+        None => builder,
+    };
+    if let Some(min) = options.ssl_version_min.and_then(ssl_version) {
+        builder = builder.min_tls_version(min);
+    }
+    if let Some(max) = options.ssl_version_max {
+        builder = builder.max_tls_version(ssl_version_max(max));
+    }
+    if let Some(proxy_url) = &options.proxy {
+        let mut proxy = reqwest::Proxy::all(proxy_url)?;
+        if let Some(auth) = &options.proxy_authenticate {
+            proxy = proxy.basic_auth(&auth.username, &auth.password);
+        }
+        builder = builder.proxy(proxy);
+    }
+    builder.build()
+}
+
+/// The headers of a response, readable line by line.
+pub struct Headers(io::Cursor<Vec<u8>>);
+
+impl io::Read for Headers {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        io::Read::read(&mut self.0, buf)
+    }
+}
+
+impl io::BufRead for Headers {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        io::BufRead::fill_buf(&mut self.0)
+    }
+    fn consume(&mut self, amt: usize) {
+        io::BufRead::consume(&mut self.0, amt)
+    }
+}
+
+/// The not-yet-written body of a `POST` request, filled in by the caller before the response is read.
+#[derive(Clone, Default)]
+pub struct PostBody(Rc<RefCell<Vec<u8>>>);
+
+impl io::Write for PostBody {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+enum Delayed {
+    /// The request hasn't actually been sent yet as `reqwest`'s blocking client can't stream a
+    /// request body while the response is being read, unlike curl's multi-handle. We send it for
+    /// real only once the caller starts reading the response, by which time `post_body` is complete.
+    Pending {
+        client: reqwest::blocking::Client,
+        url: String,
+        headers: Vec<String>,
+        post_body: PostBody,
+    },
+    Sent(io::Cursor<Vec<u8>>),
+}
+
+/// The body of a response, read lazily to give a `POST`'s request body a chance to be written first.
+pub struct ResponseBody {
+    state: Delayed,
+    /// The real HTTP status, known right away for a `GET` but only filled in by `ensure_sent()`
+    /// for a `POST`, once the request has actually been sent.
+    status: Cell<u16>,
+    /// Stored by `set_progress_handler()` and never called: this body does not decode sidebands, so
+    /// it reports no progress or error lines.
+    handle_progress: Option<crate::client::HandleProgress>,
+}
+
+impl ResponseBody {
+    fn ensure_sent(&mut self) -> io::Result<()> {
+        if let Delayed::Pending {
+            client,
+            url,
+            headers,
+            post_body,
+        } = &self.state
+        {
+            let mut request = client.post(url);
+            for header in headers {
+                request = add_header(request, header);
+            }
+            let body = post_body.0.borrow().clone();
+            let response = request
+                .body(body)
+                .send()
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, to_error(err)))?;
+            self.status.set(response.status().as_u16());
+            let bytes = response
+                .bytes()
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, to_error(err)))?;
+            self.state = Delayed::Sent(io::Cursor::new(bytes.to_vec()));
+        }
+        Ok(())
+    }
+
+    fn cursor(&mut self) -> &mut io::Cursor<Vec<u8>> {
+        match &mut self.state {
+            Delayed::Sent(cursor) => cursor,
+            Delayed::Pending { .. } => unreachable!("ensure_sent() must be called first"),
+        }
+    }
+}
+
+impl ResponseStatus for ResponseBody {
+    fn status(&self) -> u16 {
+        self.status.get()
+    }
+}
+
+impl io::Read for ResponseBody {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.ensure_sent()?;
+        self.cursor().read(buf)
+    }
+}
+
+impl io::BufRead for ResponseBody {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.ensure_sent()?;
+        self.cursor().fill_buf()
+    }
+    fn consume(&mut self, amt: usize) {
+        self.cursor().consume(amt)
+    }
+}
+
+impl SetProgressHandlerBufRead for ResponseBody {
+    fn set_progress_handler(&mut self, handle_progress: Option<crate::client::HandleProgress>) {
+        self.handle_progress = handle_progress;
+    }
+}
+
+fn headers_to_cursor(response: &reqwest::blocking::Response) -> Headers {
+    let mut buf = Vec::new();
+    for (name, value) in response.headers() {
+        buf.extend_from_slice(name.as_str().as_bytes());
+        buf.extend_from_slice(b": ");
+        buf.extend_from_slice(value.as_bytes());
+        buf.push(b'\n');
+    }
+    Headers(io::Cursor::new(buf))
+}
+
+fn to_error(err: reqwest::Error) -> Error {
+    Error::Detail(err.to_string())
+}
+
+fn add_header(request: reqwest::blocking::RequestBuilder, header: &str) -> reqwest::blocking::RequestBuilder {
+    match header.split_once(':') {
+        Some((name, value)) => request.header(name.trim(), value.trim()),
+        None => request,
+    }
+}
+
+impl Http for Reqwest {
+    type Headers = Headers;
+    type ResponseBody = ResponseBody;
+    type PostBody = PostBody;
+
+    fn get(
+        &mut self,
+        url: &str,
+        headers: impl IntoIterator<Item = impl AsRef<str>>,
+        options: &Options,
+    ) -> Result<GetResponse<Self::Headers, Self::ResponseBody>, Error> {
+        // `get()` is only ever used for the initial `info/refs` advertisement.
+        let client = client_for(options, true).map_err(to_error)?;
+        let mut request = client.get(url);
+        for header in headers {
+            request = add_header(request, header.as_ref());
+        }
+        for header in &options.extra_headers {
+            request = add_header(request, header);
+        }
+        let response = request.send().map_err(to_error)?;
+        let headers = headers_to_cursor(&response);
+        let status = response.status().as_u16();
+        let body = response.bytes().map_err(to_error)?.to_vec();
+        Ok(GetResponse {
+            headers,
+            body: ResponseBody {
+                state: Delayed::Sent(io::Cursor::new(body)),
+                status: Cell::new(status),
+                handle_progress: None,
+            },
+            status,
+        })
+    }
+
+    fn post(
+        &mut self,
+        url: &str,
+        headers: impl IntoIterator<Item = impl AsRef<str>>,
+        options: &Options,
+    ) -> Result<PostResponse<Self::Headers, Self::ResponseBody, Self::PostBody>, Error> {
+        // `post()` is never the initial request, so `FollowRedirects::Initial` must not follow here.
+        let client = client_for(options, false).map_err(to_error)?;
+        let mut all_headers: Vec<String> = headers.into_iter().map(|h| h.as_ref().to_owned()).collect();
+        all_headers.extend(options.extra_headers.iter().cloned());
+        let post_body = PostBody::default();
+        Ok(PostResponse {
+            headers: Headers(io::Cursor::new(Vec::new())),
+            body: ResponseBody {
+                state: Delayed::Pending {
+                    client,
+                    url: url.to_owned(),
+                    headers: all_headers,
+                    post_body: post_body.clone(),
+                },
+                status: Cell::new(0),
+                handle_progress: None,
+            },
+            post_body,
+            // `reqwest`'s blocking client can't report a status before the request body is complete;
+            // see `Delayed::Pending` above and `ResponseBody`'s `ResponseStatus` impl.
+            status: 0,
+        })
+    }
+}
@@ -0,0 +1,83 @@
+use super::options::Options;
+use crate::client::SetProgressHandlerBufRead;
+use quick_error::quick_error;
+use std::io;
+
+quick_error! {
+    #[derive(Debug)]
+    pub enum Error {
+        Detail(description: String) {
+            display("{}", description)
+        }
+        Authentication(description: String) {
+            display("Authentication failed: {}", description)
+        }
+    }
+}
+
+/// The result of a GET request, as returned by [`Http::get()`].
+pub struct GetResponse<H, B> {
+    /// The headers of the response, readable line by line.
+    pub headers: H,
+    /// The body of the response, to be decoded as the respective git service's advertisement.
+    pub body: B,
+    /// The HTTP status code of the response, needed to detect and react to `401 Unauthorized`.
+    pub status: u16,
+}
+
+/// The result of a POST request, as returned by [`Http::post()`].
+pub struct PostResponse<H, B, P> {
+    /// The headers of the response, readable line by line.
+    pub headers: H,
+    /// The body of the response, to be decoded as the respective git service's result.
+    pub body: B,
+    /// A writer to stream the request body to, which is sent as the POST is driven to completion.
+    pub post_body: P,
+    /// The HTTP status code of the response. Always `0` right after `post()` returns: a backend
+    /// cannot learn the real status before `post_body` has been written and the response read at
+    /// least once, since that's what actually drives the request to completion. Once that has
+    /// happened, `body`'s [`ResponseStatus::status()`] holds the real value - `status` itself is
+    /// never updated in place.
+    pub status: u16,
+}
+
+/// Implemented by a response body whose real HTTP status may only become known once the body has
+/// been read (or [`io::BufRead::fill_buf()`] called) at least once, as is the case for
+/// [`Http::post()`]'s lazily-sent responses (see [`PostResponse::status`]).
+pub trait ResponseStatus {
+    /// The HTTP status of the request. For a [`Http::post()`] response this is only meaningful
+    /// after the body has actually been read.
+    fn status(&self) -> u16;
+}
+
+/// An abstraction over a blocking HTTP client capable of issuing the two request kinds required to
+/// implement the smart HTTP protocol, namely `GET` for the initial service advertisement and `POST`
+/// for pushing packfile negotiation data and receiving the result in one roundtrip.
+pub trait Http {
+    /// The type to read the response headers line by line.
+    type Headers: io::BufRead;
+    /// The type to read the response body. It accepts a progress handler through
+    /// [`SetProgressHandlerBufRead::set_progress_handler`]; the curl and reqwest backends store that
+    /// handler without ever calling it.
+    type ResponseBody: io::BufRead + SetProgressHandlerBufRead + ResponseStatus;
+    /// The type to stream the request body to the server.
+    type PostBody: io::Write;
+
+    /// Issue a `GET` request to the given `url` with extra `headers`, honoring `options` such as
+    /// redirect handling, TLS version constraints or a proxy, and returning the decoded response on success.
+    fn get(
+        &mut self,
+        url: &str,
+        headers: impl IntoIterator<Item = impl AsRef<str>>,
+        options: &Options,
+    ) -> Result<GetResponse<Self::Headers, Self::ResponseBody>, Error>;
+
+    /// Issue a `POST` request to the given `url` with extra `headers`, honoring `options` such as
+    /// redirect handling, TLS version constraints or a proxy, and returning the decoded response on success.
+    fn post(
+        &mut self,
+        url: &str,
+        headers: impl IntoIterator<Item = impl AsRef<str>>,
+        options: &Options,
+    ) -> Result<PostResponse<Self::Headers, Self::ResponseBody, Self::PostBody>, Error>;
+}
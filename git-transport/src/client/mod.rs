@@ -0,0 +1,30 @@
+//! Types shared by every concrete `Transport` implementation, regardless of the wire protocol or
+//! backend used to drive it.
+
+/// What a line handler passed to [`SetProgressHandlerBufRead::set_progress_handler`] wants to
+/// happen next.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum ProgressAction {
+    /// Keep reading further lines.
+    Continue,
+    /// Stop reading now: a reader that acts on it ends the body stream as if it had hit EOF.
+    /// `git_packetline`'s sideband demuxer does not act on it, see [`HandleProgress`].
+    Terminate,
+}
+
+/// Called by a sideband-demuxing reader for each out-of-band line it decodes: `is_error` is `true`
+/// for a remote error line (band 3) and `false` for a progress line (band 2). The returned
+/// [`ProgressAction`] tells the reader whether to keep reading or to end the body stream.
+///
+/// `git_packetline`'s sideband demuxer, the only reader that installs a `HandleProgress` handler,
+/// does not act on [`ProgressAction::Terminate`], so returning it does not end the body stream.
+pub type HandleProgress = Box<dyn FnMut(bool, &[u8]) -> ProgressAction + Send>;
+
+/// Implemented by a response body that can separate out-of-band progress/error lines from the
+/// actual payload, so a [`HandleProgress`] handler can be installed to observe (and potentially
+/// cancel) them as they're decoded.
+pub trait SetProgressHandlerBufRead: std::io::BufRead {
+    /// Install `handle_progress`, replacing any previously installed handler. Pass `None` to stop
+    /// reacting to progress/error lines.
+    fn set_progress_handler(&mut self, handle_progress: Option<HandleProgress>);
+}
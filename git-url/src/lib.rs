@@ -4,6 +4,10 @@
 #[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
 pub enum Protocol {
     Ssh,
+    Git,
+    Http,
+    Https,
+    File,
 }
 
 pub mod borrowed {
@@ -34,18 +38,321 @@ pub mod borrowed {
 pub use borrowed::Url as Borrowed;
 
 pub mod parse {
-    use crate::borrowed;
+    use crate::{borrowed, borrowed::UserExpansion, Protocol};
+    use bstr::ByteSlice;
     use quick_error::quick_error;
 
     quick_error! {
         #[derive(Debug)]
         pub enum Error {
-            TBD
+            UnknownScheme(scheme: String) {
+                display("Could not identify '{}' as a valid protocol", scheme)
+            }
+            MissingHost {
+                display("Could not parse host out of url, which is required")
+            }
+            MalformedPort(port: String) {
+                display("'{}' is not a valid port number", port)
+            }
+            EmptyPath {
+                display("Paths cannot be empty")
+            }
         }
     }
 
-    pub fn parse(_url: &[u8]) -> Result<borrowed::Url, Error> {
-        unimplemented!("parse")
+    const MAX_PORT: u32 = u16::MAX as u32;
+
+    fn parse_expansion(path: &[u8]) -> Option<UserExpansion<'_>> {
+        // A leading slash is part of the path, not the tilde, e.g. in `ssh://host/~user/repo`.
+        let candidate = path.strip_prefix(b"/").unwrap_or(path);
+        if !candidate.first().map_or(false, |&b| b == b'~') {
+            return None;
+        }
+        let rest = &candidate[1..];
+        let name_len = rest.iter().position(|&b| b == b'/').unwrap_or_else(|| rest.len());
+        let name = &rest[..name_len];
+        Some(if name.is_empty() {
+            UserExpansion::Current
+        } else {
+            UserExpansion::Name(name.as_bstr())
+        })
+    }
+
+    fn parse_path(path: &[u8]) -> Result<&[u8], Error> {
+        if path.is_empty() {
+            return Err(Error::EmptyPath);
+        }
+        Ok(path)
+    }
+
+    /// Split `user@host:port` into its optional `user`, mandatory `host` and optional `port` parts.
+    fn parse_user_host_port(
+        authority: &[u8],
+    ) -> Result<(Option<&[u8]>, &[u8], Option<u32>), Error> {
+        let (user, host_and_port) = match authority.find_byte(b'@') {
+            Some(at) => (Some(&authority[..at]), &authority[at + 1..]),
+            None => (None, authority),
+        };
+        if host_and_port.is_empty() {
+            return Err(Error::MissingHost);
+        }
+        let (host, port) = match host_and_port.rfind_byte(b':') {
+            Some(colon) => {
+                let port_str = &host_and_port[colon + 1..];
+                let port = std::str::from_utf8(port_str)
+                    .ok()
+                    .and_then(|s| s.parse::<u32>().ok())
+                    .filter(|port| *port <= MAX_PORT)
+                    .ok_or_else(|| Error::MalformedPort(port_str.as_bstr().to_string()))?;
+                (&host_and_port[..colon], Some(port))
+            }
+            None => (host_and_port, None),
+        };
+        if host.is_empty() {
+            return Err(Error::MissingHost);
+        }
+        Ok((user, host, port))
+    }
+
+    fn protocol_from_scheme(scheme: &[u8]) -> Result<Protocol, Error> {
+        Ok(match scheme {
+            b"ssh" => Protocol::Ssh,
+            b"git" => Protocol::Git,
+            b"http" => Protocol::Http,
+            b"https" => Protocol::Https,
+            b"file" => Protocol::File,
+            _ => return Err(Error::UnknownScheme(scheme.as_bstr().to_string())),
+        })
+    }
+
+    /// Returns the index of the `://` that follows a leading URL scheme, if `url` starts with one.
+    /// A scheme looks like `[A-Za-z][A-Za-z0-9+.-]*`, so this is anchored at the very start of `url`
+    /// rather than scanning for `://` anywhere in the string - otherwise a bare local path that
+    /// merely *contains* `://` further along, e.g. `/mirror/weird://name`, would be misrouted into
+    /// scheme parsing instead of being treated as a local path.
+    fn scheme_end(url: &[u8]) -> Option<usize> {
+        if !url.first().map_or(false, u8::is_ascii_alphabetic) {
+            return None;
+        }
+        let end = url
+            .iter()
+            .position(|&b| !(b.is_ascii_alphanumeric() || b == b'+' || b == b'-' || b == b'.'))
+            .unwrap_or(url.len());
+        url[end..].starts_with(b"://").then(|| end)
+    }
+
+    /// Returns true if `url` looks like the scp-like shorthand `[user@]host:path`, i.e. it has
+    /// a colon that appears before the first slash (if any), the way `git` itself decides.
+    fn looks_like_scp(url: &[u8]) -> bool {
+        let colon = url.find_byte(b':');
+        let slash = url.find_byte(b'/');
+        match (colon, slash) {
+            (Some(colon), Some(slash)) => colon < slash,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// Parse the given `url` into a [`Url`][borrowed::Url], supporting `ssh://`, `git://`, `http(s)://`,
+    /// `file://`, bare local paths and the scp-like `user@host:path` shorthand.
+    pub fn parse(url: &[u8]) -> Result<borrowed::Url<'_>, Error> {
+        if let Some(scheme_end) = scheme_end(url) {
+            let scheme = &url[..scheme_end];
+            let protocol = protocol_from_scheme(scheme)?;
+            let rest = &url[scheme_end + 3..];
+
+            if protocol == Protocol::File {
+                let path = parse_path(rest)?;
+                return Ok(borrowed::Url {
+                    protocol,
+                    user: None,
+                    host: None,
+                    port: None,
+                    path: path.as_bstr(),
+                    expand_user: parse_expansion(path),
+                });
+            }
+
+            let (authority, path) = match rest.find_byte(b'/') {
+                Some(slash) => (&rest[..slash], &rest[slash..]),
+                None => (rest, &[][..]),
+            };
+            let (user, host, port) = parse_user_host_port(authority)?;
+            let path = parse_path(path)?;
+            Ok(borrowed::Url {
+                protocol,
+                user: user.map(ByteSlice::as_bstr),
+                host: Some(host.as_bstr()),
+                port,
+                path: path.as_bstr(),
+                expand_user: parse_expansion(path),
+            })
+        } else if looks_like_scp(url) {
+            let colon = url.find_byte(b':').expect("looks_like_scp() found one");
+            let (authority, path) = (&url[..colon], &url[colon + 1..]);
+            let (user, host, _port) = parse_user_host_port(authority)?;
+            let path = parse_path(path)?;
+            Ok(borrowed::Url {
+                protocol: Protocol::Ssh,
+                user: user.map(ByteSlice::as_bstr),
+                host: Some(host.as_bstr()),
+                port: None,
+                path: path.as_bstr(),
+                expand_user: parse_expansion(path),
+            })
+        } else {
+            let path = parse_path(url)?;
+            Ok(borrowed::Url {
+                protocol: Protocol::File,
+                user: None,
+                host: None,
+                port: None,
+                path: path.as_bstr(),
+                expand_user: parse_expansion(path),
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::borrowed::{UserExpansion, Url};
+        use bstr::ByteSlice;
+
+        fn url(
+            protocol: Protocol,
+            user: Option<&'static str>,
+            host: Option<&'static str>,
+            port: Option<u32>,
+            path: &'static str,
+            expand_user: Option<UserExpansion<'static>>,
+        ) -> Url<'static> {
+            Url {
+                protocol,
+                user: user.map(|u| u.as_bytes().as_bstr()),
+                host: host.map(|h| h.as_bytes().as_bstr()),
+                port,
+                path: path.as_bytes().as_bstr(),
+                expand_user,
+            }
+        }
+
+        #[test]
+        fn ssh_url_with_user_and_port() {
+            assert_eq!(
+                parse(b"ssh://git@host.xz:2222/path/to/repo.git").unwrap(),
+                url(Protocol::Ssh, Some("git"), Some("host.xz"), Some(2222), "/path/to/repo.git", None)
+            );
+        }
+
+        #[test]
+        fn git_url() {
+            assert_eq!(
+                parse(b"git://host.xz/path/to/repo.git").unwrap(),
+                url(Protocol::Git, None, Some("host.xz"), None, "/path/to/repo.git", None)
+            );
+        }
+
+        #[test]
+        fn http_and_https_url() {
+            assert_eq!(
+                parse(b"http://example.com/repo.git").unwrap(),
+                url(Protocol::Http, None, Some("example.com"), None, "/repo.git", None)
+            );
+            assert_eq!(
+                parse(b"https://example.com/repo.git").unwrap(),
+                url(Protocol::Https, None, Some("example.com"), None, "/repo.git", None)
+            );
+        }
+
+        #[test]
+        fn file_url() {
+            assert_eq!(
+                parse(b"file:///path/to/repo.git").unwrap(),
+                url(Protocol::File, None, None, None, "/path/to/repo.git", None)
+            );
+        }
+
+        #[test]
+        fn bare_local_path() {
+            assert_eq!(
+                parse(b"/path/to/repo.git").unwrap(),
+                url(Protocol::File, None, None, None, "/path/to/repo.git", None)
+            );
+        }
+
+        #[test]
+        fn scp_like_shorthand() {
+            assert_eq!(
+                parse(b"git@host.xz:path/to/repo.git").unwrap(),
+                url(Protocol::Ssh, Some("git"), Some("host.xz"), None, "path/to/repo.git", None)
+            );
+        }
+
+        #[test]
+        fn user_expansion_current_and_named() {
+            assert_eq!(
+                parse(b"ssh://git@host.xz/~/repo.git").unwrap(),
+                url(
+                    Protocol::Ssh,
+                    Some("git"),
+                    Some("host.xz"),
+                    None,
+                    "/~/repo.git",
+                    Some(UserExpansion::Current)
+                )
+            );
+            assert_eq!(
+                parse(b"git@host.xz:~user/repo.git").unwrap(),
+                url(
+                    Protocol::Ssh,
+                    Some("git"),
+                    Some("host.xz"),
+                    None,
+                    "~user/repo.git",
+                    Some(UserExpansion::Name("user".as_bytes().as_bstr()))
+                )
+            );
+        }
+
+        #[test]
+        fn unknown_scheme_is_an_error() {
+            assert!(matches!(parse(b"ftp://host.xz/repo.git"), Err(Error::UnknownScheme(_))));
+        }
+
+        #[test]
+        fn missing_host_is_an_error() {
+            assert!(matches!(parse(b"ssh:///path/to/repo.git"), Err(Error::MissingHost)));
+        }
+
+        #[test]
+        fn malformed_port_is_an_error() {
+            assert!(matches!(
+                parse(b"ssh://host.xz:notaport/repo.git"),
+                Err(Error::MalformedPort(_))
+            ));
+        }
+
+        #[test]
+        fn empty_path_is_an_error() {
+            assert!(matches!(parse(b"ssh://host.xz"), Err(Error::EmptyPath)));
+        }
+
+        #[test]
+        fn out_of_range_port_is_an_error() {
+            assert!(matches!(
+                parse(b"ssh://host.xz:999999999/repo.git"),
+                Err(Error::MalformedPort(_))
+            ));
+        }
+
+        #[test]
+        fn local_path_containing_a_scheme_like_substring_is_not_a_scheme() {
+            assert_eq!(
+                parse(b"/mirror/weird://name").unwrap(),
+                url(Protocol::File, None, None, None, "/mirror/weird://name", None)
+            );
+        }
     }
 }
 